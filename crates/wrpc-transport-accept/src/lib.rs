@@ -0,0 +1,77 @@
+//! Transport-agnostic connection acceptance shared by wRPC server backends.
+//!
+//! `wrpc_transport_web` and friends each know how to negotiate one kind of
+//! connection (WebTransport, raw QUIC, WebSocket, ...) and speak wRPC's
+//! invocation framing over it. This crate factors out only the first half --
+//! accepting a connection and exposing its bidirectional/unidirectional
+//! streams as plain [`Send`] `AsyncRead`/`AsyncWrite` halves -- behind
+//! [`AcceptTransport`] and [`Connection`], so backends can be selected at
+//! construction time (see [`webtransport`], [`quic`], [`accept_any`]).
+//!
+//! It does **not** implement wRPC's invocation dispatch (the real
+//! `wrpc-transport` crate's `Invoke`/`Serve` traits that `bindings::serve`
+//! consumes) -- note the distinct crate name, `wrpc-transport-accept`, to
+//! avoid colliding with that crate. Plugging an [`AcceptTransport`] backend
+//! into `bindings::serve` still requires an adapter implementing those
+//! traits over the accepted [`Connection`]; none is provided here.
+
+use std::io;
+use std::pin::Pin;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+pub mod webtransport;
+
+#[cfg(feature = "quic")]
+pub mod quic;
+
+mod multi;
+pub use multi::{accept_any, boxed};
+
+pub mod router;
+pub use router::{Policy, Router};
+
+/// One half of a stream opened or accepted over a [`Connection`]
+pub type RecvStream = Pin<Box<dyn AsyncRead + Send>>;
+
+/// The other half of a stream opened or accepted over a [`Connection`]
+pub type SendStream = Pin<Box<dyn AsyncWrite + Send>>;
+
+/// A connection established by some transport backend, exposing its
+/// bidirectional and unidirectional streams uniformly regardless of the
+/// underlying implementation (WebTransport, QUIC, ...)
+#[async_trait::async_trait]
+pub trait Connection: Send {
+    /// Opens a new bidirectional stream
+    async fn open_bi(&self) -> io::Result<(SendStream, RecvStream)>;
+
+    /// Accepts a bidirectional stream opened by the peer
+    async fn accept_bi(&self) -> io::Result<(SendStream, RecvStream)>;
+
+    /// Opens a new unidirectional stream
+    async fn open_uni(&self) -> io::Result<SendStream>;
+
+    /// Accepts a unidirectional stream opened by the peer
+    async fn accept_uni(&self) -> io::Result<RecvStream>;
+}
+
+/// A backend that listens for and establishes connections of some
+/// [`Connection`] implementation. Implementations perform whatever
+/// handshake their protocol requires (e.g. the WebTransport CONNECT
+/// exchange) and return a connection ready for wRPC framing
+#[async_trait::async_trait]
+pub trait AcceptTransport: Send + Sync {
+    /// The connection type this backend produces
+    type Connection: Connection;
+
+    /// Waits for and establishes the next incoming connection
+    async fn accept(&self) -> anyhow::Result<Self::Connection>;
+}
+
+/// A [`Connection`] trait object, erasing the concrete backend type
+pub type BoxConnection = Box<dyn Connection>;
+
+/// An [`AcceptTransport`] trait object yielding [`BoxConnection`]s, so a
+/// single server can accept connections from multiple heterogeneous
+/// backends concurrently (see [`accept_any`])
+pub type BoxAcceptor = Box<dyn AcceptTransport<Connection = BoxConnection>>;
@@ -0,0 +1,52 @@
+use futures::stream::{self, BoxStream, StreamExt as _};
+
+use crate::{AcceptTransport, BoxAcceptor, BoxConnection};
+
+/// Erases a concrete [`AcceptTransport`] backend into a [`BoxAcceptor`], so
+/// heterogeneous backends (WebTransport, raw QUIC, ...) can be collected into
+/// one `Vec` and passed to [`accept_any`]
+pub fn boxed<A>(acceptor: A) -> BoxAcceptor
+where
+    A: AcceptTransport + 'static,
+    A::Connection: 'static,
+{
+    struct Erased<A>(A);
+
+    #[async_trait::async_trait]
+    impl<A> AcceptTransport for Erased<A>
+    where
+        A: AcceptTransport + 'static,
+        A::Connection: 'static,
+    {
+        type Connection = BoxConnection;
+
+        async fn accept(&self) -> anyhow::Result<Self::Connection> {
+            let conn = self.0.accept().await?;
+            Ok(Box::new(conn))
+        }
+    }
+
+    Box::new(Erased(acceptor))
+}
+
+/// Turns a set of [`BoxAcceptor`]s into a single stream of accepted
+/// connections, polling every backend concurrently via
+/// [`select_all`](futures::stream::select_all) so a server can listen on
+/// WebTransport, raw QUIC and any other registered backend at once
+///
+/// ```ignore
+/// let accept = accept_any(vec![
+///     boxed(WebTransportAcceptor::from(webtransport_endpoint)),
+///     boxed(QuicAcceptor::from(quic_endpoint)),
+/// ]);
+/// ```
+pub fn accept_any(acceptors: Vec<BoxAcceptor>) -> BoxStream<'static, anyhow::Result<BoxConnection>> {
+    stream::select_all(acceptors.into_iter().map(|acceptor| {
+        stream::unfold(acceptor, |acceptor| async move {
+            let conn = acceptor.accept().await;
+            Some((conn, acceptor))
+        })
+        .boxed()
+    }))
+    .boxed()
+}
@@ -0,0 +1,72 @@
+//! [`AcceptTransport`] backed by raw QUIC via [`quinn`]
+//!
+//! Dropped in next to [`crate::webtransport`] so a server can accept raw QUIC
+//! connections alongside (or instead of) WebTransport ones, see
+//! [`crate::accept_any`]. As with [`crate::webtransport`], this only accepts
+//! connections and exposes their streams -- wiring the result into
+//! `bindings::serve` needs an `Invoke`/`Serve` adapter this crate doesn't
+//! provide
+
+use std::io;
+
+use anyhow::Context as _;
+
+use crate::{AcceptTransport, Connection, RecvStream, SendStream};
+
+/// Accepts raw QUIC connections over a bound [`quinn::Endpoint`]
+pub struct QuicAcceptor(quinn::Endpoint);
+
+impl From<quinn::Endpoint> for QuicAcceptor {
+    fn from(ep: quinn::Endpoint) -> Self {
+        Self(ep)
+    }
+}
+
+#[async_trait::async_trait]
+impl AcceptTransport for QuicAcceptor {
+    type Connection = quinn::Connection;
+
+    async fn accept(&self) -> anyhow::Result<Self::Connection> {
+        let incoming = self
+            .0
+            .accept()
+            .await
+            .context("QUIC endpoint closed")?;
+        incoming.await.context("failed to establish QUIC connection")
+    }
+}
+
+#[async_trait::async_trait]
+impl Connection for quinn::Connection {
+    async fn open_bi(&self) -> io::Result<(SendStream, RecvStream)> {
+        let (send, recv) = self
+            .open_bi()
+            .await
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        Ok((Box::pin(send), Box::pin(recv)))
+    }
+
+    async fn accept_bi(&self) -> io::Result<(SendStream, RecvStream)> {
+        let (send, recv) = self
+            .accept_bi()
+            .await
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        Ok((Box::pin(send), Box::pin(recv)))
+    }
+
+    async fn open_uni(&self) -> io::Result<SendStream> {
+        let send = self
+            .open_uni()
+            .await
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        Ok(Box::pin(send))
+    }
+
+    async fn accept_uni(&self) -> io::Result<RecvStream> {
+        let recv = self
+            .accept_uni()
+            .await
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        Ok(Box::pin(recv))
+    }
+}
@@ -0,0 +1,251 @@
+//! Per-export routing and graceful shutdown for `bindings::serve` invocations
+//!
+//! Every wRPC server ends up hand-rolling the same `select!` loop: conflate
+//! the `(instance, name, invocations)` triples `bindings::serve` returns into
+//! one stream, spawn a task per accepted invocation, and tear down on ^C.
+//! [`Router`] packages that up, letting callers register a concurrency
+//! limit and/or timeout per `(instance, name)` instead of iterating the
+//! triples by hand.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::stream::{select_all, BoxStream};
+use futures::{Stream, StreamExt as _};
+use tokio::sync::{oneshot, Semaphore};
+use tokio::task::JoinSet;
+use tracing::{debug, error, warn};
+
+/// Handling policy for one `(instance, name)` export
+#[derive(Debug, Clone, Default)]
+pub struct Policy {
+    /// Maximum invocations of this export handled concurrently; unlimited if `None`
+    pub concurrency_limit: Option<usize>,
+    /// Time allowed for a single invocation to complete before it's abandoned
+    pub timeout: Option<Duration>,
+}
+
+/// Registers per-export [`Policy`]s and, via [`Router::serve`], owns the
+/// `JoinSet` that runs accepted invocations
+#[derive(Default)]
+pub struct Router {
+    policies: HashMap<(String, String), Policy>,
+}
+
+impl Router {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `policy` for `instance`/`name`, overriding the default
+    /// (unlimited concurrency, no timeout)
+    pub fn register(
+        &mut self,
+        instance: impl Into<String>,
+        name: impl Into<String>,
+        policy: Policy,
+    ) -> &mut Self {
+        self.policies.insert((instance.into(), name.into()), policy);
+        self
+    }
+
+    /// Starts routing `invocations` (as returned by `bindings::serve`, with
+    /// instance and name converted to owned `String`s) to their registered
+    /// policies. Returns a handle used to [`RunningRouter::shutdown`]
+    pub fn serve<S, F>(self, invocations: Vec<(String, String, S)>) -> RunningRouter
+    where
+        S: Stream<Item = anyhow::Result<F>> + Send + 'static,
+        F: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        let semaphores: HashMap<(String, String), Arc<Semaphore>> = invocations
+            .iter()
+            .filter_map(|(instance, name, _)| {
+                let key = (instance.clone(), name.clone());
+                self.policies
+                    .get(&key)
+                    .and_then(|policy| policy.concurrency_limit)
+                    .map(|n| (key, Arc::new(Semaphore::new(n))))
+            })
+            .collect();
+        let timeouts: HashMap<(String, String), Duration> = self
+            .policies
+            .iter()
+            .filter_map(|(key, policy)| policy.timeout.map(|timeout| (key.clone(), timeout)))
+            .collect();
+
+        let (shutdown_tx, mut shutdown_rx) = oneshot::channel::<Duration>();
+        let handle = tokio::spawn(async move {
+            let mut invocations: BoxStream<(String, String, anyhow::Result<F>)> = select_all(
+                invocations.into_iter().map(|(instance, name, invocations)| {
+                    invocations
+                        .map(move |res| (instance.clone(), name.clone(), res))
+                        .boxed()
+                }),
+            )
+            .boxed();
+            let mut tasks = JoinSet::<anyhow::Result<()>>::new();
+            loop {
+                tokio::select! {
+                    Some((instance, name, res)) = invocations.next() => {
+                        match res {
+                            Ok(fut) => {
+                                debug!(instance, name, "invocation accepted");
+                                let key = (instance.clone(), name.clone());
+                                let sem = semaphores.get(&key).map(Arc::clone);
+                                let timeout = timeouts.get(&key).copied();
+                                tasks.spawn(async move {
+                                    // Acquired inside the spawned task, not the `select!` branch above,
+                                    // so an exhausted permit for this export stalls only this task --
+                                    // never the dispatch loop's ability to accept other invocations,
+                                    // join finished tasks, or observe `shutdown_rx`
+                                    let _permit = match sem {
+                                        Some(sem) => {
+                                            Some(sem.acquire_owned().await.expect(
+                                                "router semaphore is never closed while its task is running",
+                                            ))
+                                        }
+                                        None => None,
+                                    };
+                                    match timeout {
+                                        Some(d) => tokio::time::timeout(d, fut)
+                                            .await
+                                            .unwrap_or_else(|_| anyhow::bail!("invocation timed out after {d:?}")),
+                                        None => fut.await,
+                                    }
+                                });
+                            }
+                            Err(err) => warn!(?err, instance, name, "failed to accept invocation"),
+                        }
+                    }
+                    Some(res) = tasks.join_next() => {
+                        match res {
+                            Ok(Err(err)) => warn!(?err, "failed to handle invocation"),
+                            Err(err) => error!(?err, "failed to join invocation task"),
+                            Ok(Ok(())) => {}
+                        }
+                    }
+                    deadline = &mut shutdown_rx => {
+                        let deadline = deadline.unwrap_or_default();
+                        let drain = async {
+                            while let Some(res) = tasks.join_next().await {
+                                if let Err(err) = res {
+                                    error!(?err, "failed to join invocation task");
+                                }
+                            }
+                        };
+                        if tokio::time::timeout(deadline, drain).await.is_err() {
+                            warn!(?deadline, "shutdown deadline elapsed; aborting in-flight invocations");
+                            tasks.abort_all();
+                        }
+                        return;
+                    }
+                }
+            }
+        });
+
+        RunningRouter { handle, shutdown_tx }
+    }
+}
+
+/// A [`Router`] actively accepting and dispatching invocations
+pub struct RunningRouter {
+    handle: tokio::task::JoinHandle<()>,
+    shutdown_tx: oneshot::Sender<Duration>,
+}
+
+impl RunningRouter {
+    /// Stops accepting new invocations and waits up to `deadline` for
+    /// in-flight ones to finish, aborting any still running past it
+    pub async fn shutdown(self, deadline: Duration) {
+        let _ = self.shutdown_tx.send(deadline);
+        let _ = self.handle.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use futures::{stream, FutureExt as _, StreamExt as _};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn concurrency_limit_on_one_export_does_not_stall_others() {
+        // Two invocations of `a` exhaust its limit-1 semaphore and never complete;
+        // a single invocation of `b` (no limit) must still be dispatched promptly
+        // Both streams are boxed so they share the single stream type `serve` takes
+        let a = stream::iter([
+            Ok(futures::future::pending::<anyhow::Result<()>>().boxed()),
+            Ok(futures::future::pending::<anyhow::Result<()>>().boxed()),
+        ])
+        .boxed();
+        let (b_done_tx, b_done_rx) = tokio::sync::oneshot::channel();
+        let mut b_done_tx = Some(b_done_tx);
+        let b = stream::iter([Ok(async move {
+            let _ = b_done_tx.take().unwrap().send(());
+            Ok(())
+        }
+        .boxed())])
+        .boxed();
+
+        let mut router = Router::new();
+        router.register(
+            "ns",
+            "a",
+            Policy {
+                concurrency_limit: Some(1),
+                timeout: None,
+            },
+        );
+        let running = router.serve(vec![
+            ("ns".to_string(), "a".to_string(), a),
+            ("ns".to_string(), "b".to_string(), b),
+        ]);
+
+        tokio::time::timeout(Duration::from_millis(500), b_done_rx)
+            .await
+            .expect("b's invocation should dispatch even though a's semaphore is exhausted")
+            .unwrap();
+
+        running.shutdown(Duration::from_millis(50)).await;
+    }
+
+    #[tokio::test]
+    async fn timeout_abandons_slow_invocation_before_shutdown_deadline() {
+        let reached_end = Arc::new(AtomicBool::new(false));
+        let reached_end_handle = Arc::clone(&reached_end);
+        let slow = async move {
+            futures::future::pending::<()>().await;
+            reached_end_handle.store(true, Ordering::SeqCst);
+            Ok(())
+        };
+        let invocations = stream::iter([Ok(slow)]);
+
+        let mut router = Router::new();
+        router.register(
+            "ns",
+            "slow",
+            Policy {
+                concurrency_limit: None,
+                timeout: Some(Duration::from_millis(20)),
+            },
+        );
+        let running = router.serve(vec![("ns".to_string(), "slow".to_string(), invocations)]);
+
+        // Long enough for the per-invocation timeout to fire, short enough that
+        // a missing timeout (i.e. an un-abandoned, pending-forever task) would
+        // force `shutdown` below to hit its own deadline and abort instead
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!reached_end.load(Ordering::SeqCst));
+
+        let started = std::time::Instant::now();
+        running.shutdown(Duration::from_millis(200)).await;
+        assert!(
+            started.elapsed() < Duration::from_millis(200),
+            "the timed-out invocation should already be gone, so shutdown must not wait out its deadline"
+        );
+    }
+}
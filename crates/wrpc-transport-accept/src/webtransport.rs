@@ -0,0 +1,57 @@
+//! [`AcceptTransport`] backed by [`wtransport`]
+
+use std::io;
+
+use anyhow::Context as _;
+
+use crate::{AcceptTransport, Connection, RecvStream, SendStream};
+
+/// Accepts WebTransport connections over a bound [`wtransport::Endpoint`],
+/// handling the CONNECT handshake before handing back a ready connection
+pub struct WebTransportAcceptor(wtransport::Endpoint<wtransport::endpoint::endpoint_side::Server>);
+
+impl From<wtransport::Endpoint<wtransport::endpoint::endpoint_side::Server>> for WebTransportAcceptor {
+    fn from(ep: wtransport::Endpoint<wtransport::endpoint::endpoint_side::Server>) -> Self {
+        Self(ep)
+    }
+}
+
+#[async_trait::async_trait]
+impl AcceptTransport for WebTransportAcceptor {
+    type Connection = wtransport::Connection;
+
+    async fn accept(&self) -> anyhow::Result<Self::Connection> {
+        let req = self
+            .0
+            .accept()
+            .await
+            .await
+            .context("failed to accept WebTransport connection")?;
+        req.accept()
+            .await
+            .context("failed to establish WebTransport connection")
+    }
+}
+
+#[async_trait::async_trait]
+impl Connection for wtransport::Connection {
+    async fn open_bi(&self) -> io::Result<(SendStream, RecvStream)> {
+        let (send, recv) = self.open_bi().await?.await?;
+        Ok((Box::pin(send), Box::pin(recv)))
+    }
+
+    async fn accept_bi(&self) -> io::Result<(SendStream, RecvStream)> {
+        let (send, recv) = self.accept_bi().await?;
+        Ok((Box::pin(send), Box::pin(recv)))
+    }
+
+    async fn open_uni(&self) -> io::Result<SendStream> {
+        let send = self.open_uni().await?.await?;
+        Ok(Box::pin(send))
+    }
+
+    async fn accept_uni(&self) -> io::Result<RecvStream> {
+        let recv = self.accept_uni().await?;
+        Ok(Box::pin(recv))
+    }
+}
@@ -0,0 +1,38 @@
+//! Trust-configured client construction for `wrpc-transport-web`.
+//!
+//! Note the distinct crate name, `wrpc-transport-web-ext`, to avoid
+//! colliding with the real `wrpc-transport-web` crate this one augments:
+//! [`connect`] takes a [`trust::TrustConfig`] and hands back a ready
+//! `wrpc_transport_web::Client`, so callers don't have to hand-assemble a
+//! `rustls::ClientConfig` from [`trust::TrustConfig::root_store`] themselves.
+
+use anyhow::Context as _;
+use rustls::version::TLS13;
+
+pub mod trust;
+
+use trust::TrustConfig;
+
+/// Dials `addr` over WebTransport/QUIC, verifying the peer's certificate
+/// against the root store `trust` describes, and returns a ready
+/// `wrpc_transport_web::Client`
+pub async fn connect(addr: &str, trust: TrustConfig) -> anyhow::Result<wrpc_transport_web::Client> {
+    let roots = trust.root_store().context("failed to build trust root store")?;
+    let conf = rustls::ClientConfig::builder_with_protocol_versions(&[&TLS13])
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+    let ep = wtransport::Endpoint::client(
+        wtransport::ClientConfig::builder()
+            .with_bind_default()
+            .with_custom_tls(conf)
+            .build(),
+    )
+    .context("failed to create client endpoint")?;
+
+    let conn = ep
+        .connect(addr)
+        .await
+        .with_context(|| format!("failed to connect to `{addr}`"))?;
+    Ok(wrpc_transport_web::Client::from(conn))
+}
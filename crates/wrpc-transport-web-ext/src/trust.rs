@@ -0,0 +1,125 @@
+//! Trust-anchor configuration for `wrpc_transport_web::Client`
+//!
+//! [`TrustConfig`] gives callers a first-class choice of root store -- the
+//! OS trust store, the bundled Mozilla set, or an explicit PEM bundle --
+//! consumed by [`crate::connect`] to build a ready `Client` in one call.
+
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use rustls::pki_types::CertificateDer;
+use rustls::RootCertStore;
+
+/// Where [`crate::connect`] sources the roots it trusts when verifying a
+/// peer's certificate. Defaults to [`TrustConfig::NativeRoots`]
+#[derive(Debug, Clone)]
+pub enum TrustConfig {
+    /// The OS trust store, loaded via `rustls-native-certs`
+    NativeRoots,
+    /// The bundled Mozilla root set from `webpki-roots`
+    WebPkiRoots,
+    /// An explicit PEM file of CA certificates
+    Pem(PathBuf),
+}
+
+impl Default for TrustConfig {
+    fn default() -> Self {
+        Self::NativeRoots
+    }
+}
+
+impl TrustConfig {
+    /// Builds the `rustls` root store this configuration describes
+    pub fn root_store(&self) -> anyhow::Result<RootCertStore> {
+        match self {
+            Self::NativeRoots => native_roots(),
+            Self::WebPkiRoots => Ok(webpki_roots()),
+            Self::Pem(path) => pem_roots(path),
+        }
+    }
+}
+
+fn native_roots() -> anyhow::Result<RootCertStore> {
+    let certs = rustls_native_certs::load_native_certs();
+    for err in &certs.errors {
+        tracing::warn!(?err, "failed to load a native certificate");
+    }
+    let mut roots = RootCertStore::empty();
+    for cert in certs.certs {
+        // Some OS trust stores contain CAs that don't parse as a valid
+        // `TrustAnchor` (e.g. non-compliant extensions); skip rather than fail
+        match webpki_trust_anchor(&cert) {
+            Ok(()) => roots
+                .add(cert)
+                .context("failed to add native certificate to root store")?,
+            Err(err) => tracing::debug!(?err, "skipping CA that failed `TrustAnchor` parsing"),
+        }
+    }
+    Ok(roots)
+}
+
+/// Confirms `cert` parses as a valid `TrustAnchor`, without keeping it around;
+/// some OS trust stores ship CAs rustls can't build an anchor from
+fn webpki_trust_anchor(cert: &CertificateDer<'_>) -> anyhow::Result<()> {
+    webpki::anchor_from_trusted_cert(cert).context("not a valid trust anchor")?;
+    Ok(())
+}
+
+fn webpki_roots() -> RootCertStore {
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    roots
+}
+
+fn pem_roots(path: &std::path::Path) -> anyhow::Result<RootCertStore> {
+    let pem = std::fs::read(path)
+        .with_context(|| format!("failed to read CA bundle at `{}`", path.display()))?;
+    let mut roots = RootCertStore::empty();
+    for cert in rustls_pemfile::certs(&mut &pem[..]) {
+        let cert = cert.context("failed to parse CA certificate")?;
+        roots
+            .add(cert)
+            .context("failed to add CA certificate to root store")?;
+    }
+    Ok(roots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn webpki_roots_store_is_non_empty() {
+        let roots = TrustConfig::WebPkiRoots.root_store().unwrap();
+        assert!(roots.len() > 0);
+    }
+
+    #[test]
+    fn pem_roots_store_contains_exactly_the_bundled_cert() {
+        let CertifiedKeyForTest { pem } = self_signed_cert_pem();
+        let ca_path = std::env::temp_dir().join(format!("wrpc-trust-test-{}.pem", std::process::id()));
+        std::fs::write(&ca_path, &pem).unwrap();
+
+        let roots = TrustConfig::Pem(ca_path.clone()).root_store().unwrap();
+        std::fs::remove_file(&ca_path).unwrap();
+        assert_eq!(roots.len(), 1);
+    }
+
+    #[test]
+    fn pem_roots_rejects_missing_file() {
+        let err = TrustConfig::Pem(PathBuf::from("/no/such/file.pem"))
+            .root_store()
+            .unwrap_err();
+        assert!(err.to_string().contains("failed to read CA bundle"));
+    }
+
+    struct CertifiedKeyForTest {
+        pem: String,
+    }
+
+    fn self_signed_cert_pem() -> CertifiedKeyForTest {
+        let rcgen::CertifiedKey { cert, .. } =
+            rcgen::generate_simple_self_signed(["localhost".to_string()]).unwrap();
+        CertifiedKeyForTest { pem: cert.pem() }
+    }
+}
@@ -0,0 +1,135 @@
+//! Length-prefixed multiplexing of wRPC's logical streams over one WebSocket
+//!
+//! Every binary WebSocket message carries exactly one [`Frame`]:
+//!
+//! ```text
+//! +-----------+-----------+-----------------+-----------+
+//! | stream_id | frame_type|   payload_len   |  payload  |
+//! |   u32 BE  |    u8     |     u32 BE      |  N bytes  |
+//! +-----------+-----------+-----------------+-----------+
+//! ```
+//!
+//! `stream_id` is assigned by the opener (odd for client-opened, even for
+//! server-opened, so the two ends never pick the same ID) and scopes every other frame
+//! to one logical bidirectional or unidirectional stream.
+
+use std::io;
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameType {
+    /// Opens a new bidirectional stream
+    OpenBi = 0,
+    /// Opens a new unidirectional stream
+    OpenUni = 1,
+    /// Carries payload for an already-open stream
+    Data = 2,
+    /// Closes the sender's half of a stream
+    Close = 3,
+}
+
+impl TryFrom<u8> for FrameType {
+    type Error = io::Error;
+
+    fn try_from(b: u8) -> Result<Self, Self::Error> {
+        match b {
+            0 => Ok(Self::OpenBi),
+            1 => Ok(Self::OpenUni),
+            2 => Ok(Self::Data),
+            3 => Ok(Self::Close),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unknown wRPC/WS frame type `{b}`"),
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub stream_id: u32,
+    pub ty: FrameType,
+    pub payload: Bytes,
+}
+
+impl Frame {
+    pub fn encode(&self) -> Bytes {
+        let mut buf = BytesMut::with_capacity(4 + 1 + 4 + self.payload.len());
+        buf.put_u32(self.stream_id);
+        buf.put_u8(self.ty as u8);
+        buf.put_u32(self.payload.len() as u32);
+        buf.put_slice(&self.payload);
+        buf.freeze()
+    }
+
+    pub fn decode(mut buf: Bytes) -> io::Result<Self> {
+        if buf.len() < 9 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "short wRPC/WS frame"));
+        }
+        let stream_id = buf.get_u32();
+        let ty = FrameType::try_from(buf.get_u8())?;
+        let len = buf.get_u32() as usize;
+        if buf.len() != len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "wRPC/WS frame length does not match payload",
+            ));
+        }
+        Ok(Self {
+            stream_id,
+            ty,
+            payload: buf,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_encode_decode() {
+        for ty in [FrameType::OpenBi, FrameType::OpenUni, FrameType::Data, FrameType::Close] {
+            let frame = Frame {
+                stream_id: 7,
+                ty,
+                payload: Bytes::from_static(b"hello wrpc"),
+            };
+            let decoded = Frame::decode(frame.encode()).unwrap();
+            assert_eq!(decoded.stream_id, frame.stream_id);
+            assert_eq!(decoded.ty, frame.ty);
+            assert_eq!(decoded.payload, frame.payload);
+        }
+    }
+
+    #[test]
+    fn round_trips_an_empty_payload() {
+        let frame = Frame {
+            stream_id: 0,
+            ty: FrameType::Close,
+            payload: Bytes::new(),
+        };
+        let decoded = Frame::decode(frame.encode()).unwrap();
+        assert_eq!(decoded.stream_id, 0);
+        assert_eq!(decoded.ty, FrameType::Close);
+        assert!(decoded.payload.is_empty());
+    }
+
+    #[test]
+    fn decode_rejects_a_short_frame() {
+        let err = Frame::decode(Bytes::from_static(b"short")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn decode_rejects_a_mismatched_payload_length() {
+        let mut buf = BytesMut::new();
+        buf.put_u32(1);
+        buf.put_u8(FrameType::Data as u8);
+        buf.put_u32(5); // claims 5 bytes of payload
+        buf.put_slice(b"ab"); // only 2 follow
+        let err = Frame::decode(buf.freeze()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
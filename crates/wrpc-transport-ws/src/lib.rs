@@ -0,0 +1,307 @@
+//! wRPC framing multiplexed over a single WebSocket connection
+//!
+//! `wrpc_transport_web` requires WebTransport/HTTP3, which ordinary HTTPS
+//! reverse proxies and many browser environments can't speak. This crate
+//! carries the same length-prefixed stream-multiplexing idea over one
+//! long-lived `WebSocket` instead (see [`frame`]): [`Server::accept`]
+//! upgrades an incoming connection into a [`Client`], which opens/accepts
+//! bidirectional and unidirectional streams and also implements
+//! [`wrpc_transport_accept::Connection`] so it can be driven through that
+//! crate's transport-agnostic acceptor layer.
+//!
+//! Like `wrpc-transport-accept`, this crate stops at raw stream framing --
+//! it does not implement wRPC's `Invoke`/`Serve` traits, so wiring a
+//! [`Client`] into `bindings::serve` still needs an adapter this crate
+//! doesn't provide. See the `client_and_server_round_trip_a_bidirectional_stream`
+//! test below for the multiplexing behavior this crate does exercise
+//! end-to-end.
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use anyhow::Context as _;
+use bytes::Bytes;
+use futures::{SinkExt as _, StreamExt as _};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, Mutex};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+mod frame;
+mod stream;
+mod tls;
+
+use frame::{Frame, FrameType};
+use tls::MaybeTlsStream;
+pub use stream::{RecvStream, SendStream};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+struct Shared {
+    tx: Mutex<futures::stream::SplitSink<Socket, Message>>,
+    next_stream_id: AtomicU32,
+    /// Per-stream-id channel fed by the read loop, drained by that stream's [`RecvStream`]
+    inbound: Mutex<HashMap<u32, mpsc::UnboundedSender<Bytes>>>,
+    /// Streams the peer opened, awaiting a matching `accept_bi`/`accept_uni` call
+    accept_bi: mpsc::UnboundedSender<(SendStream, RecvStream)>,
+    accept_uni: mpsc::UnboundedSender<RecvStream>,
+}
+
+/// One end of a wRPC connection multiplexed over a WebSocket
+pub struct Client {
+    shared: Arc<Shared>,
+    accept_bi: Mutex<mpsc::UnboundedReceiver<(SendStream, RecvStream)>>,
+    accept_uni: Mutex<mpsc::UnboundedReceiver<RecvStream>>,
+}
+
+impl Client {
+    /// Wraps an established WebSocket connection, spawning the background
+    /// task that demultiplexes incoming frames. `server` controls whether
+    /// locally-opened stream IDs are even (server-opened) or odd
+    /// (client-opened), so the two ends never allocate the same stream ID
+    pub fn new(ws: Socket, server: bool) -> Self {
+        let (tx, mut rx) = ws.split();
+        let (accept_bi_tx, accept_bi_rx) = mpsc::unbounded_channel();
+        let (accept_uni_tx, accept_uni_rx) = mpsc::unbounded_channel();
+        let shared = Arc::new(Shared {
+            tx: Mutex::new(tx),
+            next_stream_id: AtomicU32::new(if server { 0 } else { 1 }),
+            inbound: Mutex::new(HashMap::new()),
+            accept_bi: accept_bi_tx,
+            accept_uni: accept_uni_tx,
+        });
+
+        tokio::spawn({
+            let shared = Arc::clone(&shared);
+            async move {
+                while let Some(msg) = rx.next().await {
+                    let msg = match msg {
+                        Ok(Message::Binary(data)) => data,
+                        Ok(Message::Close(_)) | Err(_) => break,
+                        Ok(_) => continue,
+                    };
+                    let frame = match Frame::decode(Bytes::from(msg)) {
+                        Ok(frame) => frame,
+                        Err(err) => {
+                            tracing::warn!(?err, "dropping malformed wRPC/WS frame");
+                            continue;
+                        }
+                    };
+                    Shared::dispatch(&shared, frame).await;
+                }
+            }
+        });
+
+        Self {
+            shared,
+            accept_bi: Mutex::new(accept_bi_rx),
+            accept_uni: Mutex::new(accept_uni_rx),
+        }
+    }
+
+    fn allocate_stream_id(&self) -> u32 {
+        self.shared.next_stream_id.fetch_add(2, Ordering::Relaxed)
+    }
+}
+
+impl Shared {
+    async fn send(&self, frame: Frame) -> io::Result<()> {
+        self.tx
+            .lock()
+            .await
+            .send(Message::Binary(frame.encode().to_vec().into()))
+            .await
+            .map_err(|err| io::Error::new(io::ErrorKind::BrokenPipe, err))
+    }
+
+    async fn register(&self, stream_id: u32) -> mpsc::UnboundedReceiver<Bytes> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.inbound.lock().await.insert(stream_id, tx);
+        rx
+    }
+
+    async fn dispatch(self: &Arc<Self>, frame: Frame) {
+        match frame.ty {
+            FrameType::OpenBi => {
+                let recv_rx = self.register(frame.stream_id).await;
+                let send = SendStream::new(Arc::clone(self), frame.stream_id);
+                let recv = RecvStream::new(recv_rx);
+                let _ = self.accept_bi.send((send, recv));
+            }
+            FrameType::OpenUni => {
+                let recv_rx = self.register(frame.stream_id).await;
+                let _ = self.accept_uni.send(RecvStream::new(recv_rx));
+            }
+            FrameType::Data => {
+                if let Some(tx) = self.inbound.lock().await.get(&frame.stream_id) {
+                    let _ = tx.send(frame.payload);
+                }
+            }
+            FrameType::Close => {
+                self.inbound.lock().await.remove(&frame.stream_id);
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl wrpc_transport_accept::Connection for Client {
+    async fn open_bi(&self) -> io::Result<(wrpc_transport_accept::SendStream, wrpc_transport_accept::RecvStream)> {
+        let stream_id = self.allocate_stream_id();
+        let recv_rx = self.shared.register(stream_id).await;
+        self.shared
+            .send(Frame {
+                stream_id,
+                ty: FrameType::OpenBi,
+                payload: Bytes::new(),
+            })
+            .await?;
+        Ok((
+            Box::pin(SendStream::new(Arc::clone(&self.shared), stream_id)),
+            Box::pin(RecvStream::new(recv_rx)),
+        ))
+    }
+
+    async fn accept_bi(&self) -> io::Result<(wrpc_transport_accept::SendStream, wrpc_transport_accept::RecvStream)> {
+        let (send, recv) = self
+            .accept_bi
+            .lock()
+            .await
+            .recv()
+            .await
+            .ok_or_else(|| io::Error::new(io::ErrorKind::BrokenPipe, "wRPC/WS connection closed"))?;
+        Ok((Box::pin(send), Box::pin(recv)))
+    }
+
+    async fn open_uni(&self) -> io::Result<wrpc_transport_accept::SendStream> {
+        let stream_id = self.allocate_stream_id();
+        self.shared
+            .send(Frame {
+                stream_id,
+                ty: FrameType::OpenUni,
+                payload: Bytes::new(),
+            })
+            .await?;
+        Ok(Box::pin(SendStream::new(Arc::clone(&self.shared), stream_id)))
+    }
+
+    async fn accept_uni(&self) -> io::Result<wrpc_transport_accept::RecvStream> {
+        let recv = self
+            .accept_uni
+            .lock()
+            .await
+            .recv()
+            .await
+            .ok_or_else(|| io::Error::new(io::ErrorKind::BrokenPipe, "wRPC/WS connection closed"))?;
+        Ok(Box::pin(recv))
+    }
+}
+
+/// Listens for incoming WebSocket connections and upgrades each one into a
+/// wRPC [`Client`]
+pub struct Server {
+    listener: TcpListener,
+    tls: Option<tokio_rustls::TlsAcceptor>,
+}
+
+impl Server {
+    /// Binds a plaintext WebSocket server; put a TLS-terminating reverse
+    /// proxy in front for production use, or see [`Self::bind_tls`]
+    pub async fn bind(addr: std::net::SocketAddr) -> anyhow::Result<Self> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("failed to bind `{addr}`"))?;
+        Ok(Self { listener, tls: None })
+    }
+
+    /// Binds a WebSocket server that terminates TLS itself rather than
+    /// relying on a reverse proxy
+    pub async fn bind_tls(
+        addr: std::net::SocketAddr,
+        conf: rustls::ServerConfig,
+    ) -> anyhow::Result<Self> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("failed to bind `{addr}`"))?;
+        Ok(Self {
+            listener,
+            tls: Some(tokio_rustls::TlsAcceptor::from(Arc::new(conf))),
+        })
+    }
+
+    /// Accepts and upgrades the next incoming connection into a wRPC [`Client`]
+    pub async fn accept(&self) -> anyhow::Result<Client> {
+        let (stream, _) = self
+            .listener
+            .accept()
+            .await
+            .context("failed to accept TCP connection")?;
+        let stream = match &self.tls {
+            Some(acceptor) => MaybeTlsStream::Tls(
+                acceptor
+                    .accept(stream)
+                    .await
+                    .context("failed to complete TLS handshake")?,
+            ),
+            None => MaybeTlsStream::Plain(stream),
+        };
+        let ws = tokio_tungstenite::accept_async(stream)
+            .await
+            .context("failed to complete WebSocket handshake")?;
+        Ok(Client::new(ws, true))
+    }
+}
+
+#[async_trait::async_trait]
+impl wrpc_transport_accept::AcceptTransport for Server {
+    type Connection = Client;
+
+    async fn accept(&self) -> anyhow::Result<Self::Connection> {
+        self.accept().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+    use tokio::net::TcpStream;
+    use wrpc_transport_accept::Connection as _;
+
+    use super::*;
+    use crate::tls::MaybeTlsStream;
+
+    #[tokio::test]
+    async fn client_and_server_round_trip_a_bidirectional_stream() {
+        let server = Server::bind("127.0.0.1:0".parse().unwrap()).await.unwrap();
+        let addr = server.listener.local_addr().unwrap();
+
+        let accept = tokio::spawn(async move { server.accept().await.unwrap() });
+
+        let tcp = TcpStream::connect(addr).await.unwrap();
+        let (ws, _) = tokio_tungstenite::client_async("ws://localhost/", MaybeTlsStream::Plain(tcp))
+            .await
+            .unwrap();
+        let client_side = Client::new(ws, false);
+        let server_side = accept.await.unwrap();
+
+        let (mut c_send, mut c_recv) = client_side.open_bi().await.unwrap();
+        let accept_bi = tokio::spawn(async move { server_side.accept_bi().await.unwrap() });
+
+        c_send.write_all(b"hello from client").await.unwrap();
+        c_send.shutdown().await.unwrap();
+
+        let (mut s_send, mut s_recv) = accept_bi.await.unwrap();
+        let mut received = Vec::new();
+        s_recv.read_to_end(&mut received).await.unwrap();
+        assert_eq!(received, b"hello from client");
+
+        s_send.write_all(b"hello from server").await.unwrap();
+        s_send.shutdown().await.unwrap();
+
+        let mut reply = Vec::new();
+        c_recv.read_to_end(&mut reply).await.unwrap();
+        assert_eq!(reply, b"hello from server");
+    }
+}
@@ -0,0 +1,117 @@
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+
+use bytes::Bytes;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::sync::mpsc;
+
+use crate::frame::{Frame, FrameType};
+use crate::Shared;
+
+/// The receiving half of a wRPC logical stream, fed by frames the socket's
+/// read loop has demultiplexed for this `stream_id`
+pub struct RecvStream {
+    rx: mpsc::UnboundedReceiver<Bytes>,
+    buf: Bytes,
+}
+
+impl RecvStream {
+    pub(crate) fn new(rx: mpsc::UnboundedReceiver<Bytes>) -> Self {
+        Self {
+            rx,
+            buf: Bytes::new(),
+        }
+    }
+}
+
+impl AsyncRead for RecvStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if self.buf.is_empty() {
+            match ready!(self.rx.poll_recv(cx)) {
+                Some(chunk) => self.buf = chunk,
+                None => return Poll::Ready(Ok(())), // peer closed its send half
+            }
+        }
+        let n = buf.remaining().min(self.buf.len());
+        buf.put_slice(&self.buf.split_to(n));
+        Poll::Ready(Ok(()))
+    }
+}
+
+type SendFuture = Pin<Box<dyn Future<Output = io::Result<()>> + Send>>;
+
+/// The sending half of a wRPC logical stream, writing frames back to the
+/// shared WebSocket connection
+pub struct SendStream {
+    shared: Arc<Shared>,
+    stream_id: u32,
+    /// The send in progress, if `poll_send` last returned `Pending`. Kept
+    /// around and re-polled rather than rebuilt on the next call -- a fresh
+    /// `Box::pin(shared.send(frame))` per poll would discard any progress
+    /// `send()` made past `start_send` and risk putting `frame` on the wire
+    /// twice once the caller retries
+    inflight: Option<SendFuture>,
+}
+
+impl SendStream {
+    pub(crate) fn new(shared: Arc<Shared>, stream_id: u32) -> Self {
+        Self {
+            shared,
+            stream_id,
+            inflight: None,
+        }
+    }
+
+    fn poll_send(
+        &mut self,
+        cx: &mut Context<'_>,
+        frame: impl FnOnce() -> Frame,
+    ) -> Poll<io::Result<()>> {
+        if self.inflight.is_none() {
+            let shared = Arc::clone(&self.shared);
+            let frame = frame();
+            self.inflight = Some(Box::pin(async move { shared.send(frame).await }));
+        }
+        let res = ready!(self.inflight.as_mut().unwrap().as_mut().poll(cx));
+        self.inflight = None;
+        Poll::Ready(res)
+    }
+}
+
+impl AsyncWrite for SendStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let stream_id = this.stream_id;
+        ready!(this.poll_send(cx, || Frame {
+            stream_id,
+            ty: FrameType::Data,
+            payload: Bytes::copy_from_slice(buf),
+        }))?;
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let stream_id = this.stream_id;
+        this.poll_send(cx, || Frame {
+            stream_id,
+            ty: FrameType::Close,
+            payload: Bytes::new(),
+        })
+    }
+}
@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use clap::Parser;
+use wrpc_transport_web_ext::trust::TrustConfig;
+
+mod bindings {
+    wit_bindgen_wrpc::generate!({
+        with: {
+            "wrpc-examples:hello/handler": generate,
+        }
+    });
+}
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// URL of the `wrpc-examples:hello/handler.hello` server to dial
+    #[arg(default_value = "https://[::1]:4433")]
+    addr: String,
+
+    /// Trust the bundled Mozilla root set instead of the OS trust store
+    #[arg(long, conflicts_with = "ca")]
+    webpki_roots: bool,
+
+    /// PEM-encoded CA bundle to trust instead of the OS trust store
+    #[arg(long, conflicts_with = "webpki_roots")]
+    ca: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt().init();
+
+    let Args {
+        addr,
+        webpki_roots,
+        ca,
+    } = Args::parse();
+
+    let trust = match (webpki_roots, ca) {
+        (true, _) => TrustConfig::WebPkiRoots,
+        (false, Some(ca)) => TrustConfig::Pem(ca),
+        (false, None) => TrustConfig::NativeRoots,
+    };
+    let wrpc = wrpc_transport_web_ext::connect(&addr, trust).await?;
+
+    let result = bindings::wrpc_examples::hello::handler::hello(&wrpc, ())
+        .await
+        .context("failed to invoke `wrpc-examples:hello/handler.hello`")?;
+    println!("{result}");
+    Ok(())
+}